@@ -0,0 +1,143 @@
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+
+use crate::request::{AurResponse, Search};
+
+// default time-to-live for a cached entry, matching the previous in-memory retainer cache.
+pub(crate) const DEFAULT_TTL_SECS: i64 = 60;
+
+// a SQLite-backed cache so hot queries survive a bot restart instead of cold-starting
+// against the AUR RPC every time.
+pub struct PersistentCache {
+    conn: Mutex<Connection>,
+    ttl_secs: i64,
+}
+
+impl PersistentCache {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        Self::open_with_ttl(path, DEFAULT_TTL_SECS)
+    }
+
+    pub fn open_with_ttl(path: &str, ttl_secs: i64) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS search_cache (
+                key TEXT PRIMARY KEY,
+                response TEXT NOT NULL,
+                inserted_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        Ok(PersistentCache {
+            conn: Mutex::new(conn),
+            ttl_secs,
+        })
+    }
+
+    // returns the cached response if a fresh row exists, pruning it lazily if it has gone stale.
+    //
+    // this (and `insert`/`prune_expired` below) does synchronous SQLite I/O while holding the
+    // `Mutex`, blocking the executor thread for the duration of the query instead of yielding via
+    // `spawn_blocking`. at this bot's traffic the lock is held for a handful of microseconds, so
+    // it isn't worth the extra complexity, but it's worth keeping in mind if query volume grows.
+    pub async fn get(&self, query: &Search) -> Option<AurResponse> {
+        let key = cache_key(query);
+        let conn = self.conn.lock().unwrap();
+        let row: Option<(String, i64)> = conn
+            .query_row(
+                "SELECT response, inserted_at FROM search_cache WHERE key = ?1",
+                params![key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .ok();
+        let (response, inserted_at) = row?;
+        if now_unix() - inserted_at > self.ttl_secs {
+            let _ = conn.execute("DELETE FROM search_cache WHERE key = ?1", params![key]);
+            return None;
+        }
+        serde_json::from_str(&response).ok()
+    }
+
+    pub async fn insert(&self, query: &Search, response: &AurResponse) {
+        let Ok(serialized) = serde_json::to_string(response) else {
+            return;
+        };
+        let key = cache_key(query);
+        let conn = self.conn.lock().unwrap();
+        let _ = conn.execute(
+            "INSERT INTO search_cache (key, response, inserted_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(key) DO UPDATE SET response = excluded.response, inserted_at = excluded.inserted_at",
+            params![key, serialized, now_unix()],
+        );
+    }
+
+    fn prune_expired(&self) {
+        let cutoff = now_unix() - self.ttl_secs;
+        let conn = self.conn.lock().unwrap();
+        let _ = conn.execute("DELETE FROM search_cache WHERE inserted_at < ?1", params![cutoff]);
+    }
+
+    // periodically sweep expired rows, analogous to retainer's `Cache::monitor`.
+    pub async fn monitor(&self, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            self.prune_expired();
+        }
+    }
+}
+
+fn cache_key(query: &Search) -> String {
+    format!("{}:{:?}:{}", query.discriminant(), query.filter(), query.as_str())
+}
+
+fn now_unix() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::Search;
+
+    fn sample_response() -> AurResponse {
+        AurResponse::Result {
+            total: 0,
+            results: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_returns_entry_within_ttl() {
+        let cache = PersistentCache::open_with_ttl(":memory:", 60).unwrap();
+        let query = Search::from("paru");
+        cache.insert(&query, &sample_response()).await;
+        assert!(cache.get(&query).await.is_some(), "expected a cache hit within the TTL window");
+    }
+
+    #[tokio::test]
+    async fn test_get_evicts_entry_past_ttl() {
+        let cache = PersistentCache::open_with_ttl(":memory:", 0).unwrap();
+        let query = Search::from("paru");
+        cache.insert(&query, &sample_response()).await;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(cache.get(&query).await.is_none(), "expected the stale entry to be treated as a miss");
+    }
+
+    #[tokio::test]
+    async fn test_prune_expired_removes_stale_rows() {
+        let cache = PersistentCache::open_with_ttl(":memory:", 0).unwrap();
+        let query = Search::from("paru");
+        cache.insert(&query, &sample_response()).await;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        cache.prune_expired();
+        let row_count: i64 = cache
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM search_cache", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(row_count, 0, "expected the expired row to be pruned");
+    }
+}
@@ -11,7 +11,14 @@ use teloxide::types::{
 };
 use teloxide::{RequestError, prelude::*, utils::command::BotCommands};
 
-use crate::request::{AurResponse, Search, Utils, cached_search};
+use crate::request::{AurResponse, Filter, Search, Utils, cached_search, fetch_pkgbuild, pkgbuild_url};
+
+// callback_data prefix used to route a "Full Info" button press back to the package it belongs to.
+const INFO_CALLBACK_PREFIX: &str = "info:";
+// callback_data prefix used to route a "PKGBUILD" button press back to the package base it belongs to.
+const PKGBUILD_CALLBACK_PREFIX: &str = "pkgbuild:";
+// Telegram caps message text at 4096 characters; past that the rendered PKGBUILD gets truncated with a link.
+const TELEGRAM_MESSAGE_LIMIT: usize = 4096;
 
 #[derive(BotCommands)]
 #[command(rename_rule = "lowercase", description = "These commands are supported:")]
@@ -42,7 +49,7 @@ pub async fn inline_queries_handler(bot: Bot, update: InlineQuery, utils: Arc<Ut
     let mut offset = update.offset.parse::<usize>().unwrap_or_default();
     let instant = Instant::now();
     let aur_response = cached_search(&utils, Search::from(&update.query)).await;
-    match &*aur_response {
+    match &aur_response {
         AurResponse::Result { total, results } => {
             info!(
                 "Query: \"{}\", total result: {}, current offset: {}, took: {}ms",
@@ -60,7 +67,14 @@ pub async fn inline_queries_handler(bot: Bot, update: InlineQuery, utils: Arc<Ut
                             InputMessageContentText::new(&package.pretty()).parse_mode(ParseMode::Html),
                         ),
                     )
-                    .description(&package.description),
+                    .description(&package.description)
+                    .reply_markup(InlineKeyboardMarkup::new([[
+                        InlineKeyboardButton::callback("📄 Full Info", format!("{}{}", INFO_CALLBACK_PREFIX, package.name)),
+                        InlineKeyboardButton::callback(
+                            "📦 PKGBUILD",
+                            format!("{}{}", PKGBUILD_CALLBACK_PREFIX, package.package_base),
+                        ),
+                    ]])),
                 ))
             });
             // increase the offset by 50 after every scroll down
@@ -106,14 +120,34 @@ pub async fn message_handler(bot: Bot, message: Message) -> Result<(), RequestEr
                     "This bot searches Packages in <a href='https://aur.archlinux.org/'>\
                      AUR repository</a>, works only in inline mode \
                 Inspired from @FDroidSearchBot\n\nCurrently supported search patterns:\n\
-                - <code>Packages</code>, search directly\n- <code>Maintainer</code>, search with <code>!m</code>\n\n\
+                - <code>Packages</code>, search by name and description (default)\n\
+                - <code>!nd</code>, search by name and description\n\
+                - <code>!m</code>, search by maintainer\n\
+                - <code>!i</code>, view full package info\n\
+                - <code>!d</code>, search by depends\n\
+                - <code>!md</code>, search by makedepends\n\
+                - <code>!od</code>, search by optdepends\n\
+                - <code>!cd</code>, search by checkdepends\n\
+                - <code>!p</code>, search by provides\n\
+                - <code>!c</code>, search by conflicts\n\
+                - <code>!r</code>, search by replaces\n\
+                - <code>!k</code>, search by keywords\n\n\
+                Append a filter to narrow results down:\n\
+                - <code>!orphan</code>, only packages with no maintainer\n\
+                - <code>!flagged</code>, only packages flagged out of date\n\n\
                 <a href='https://github.com/alenpaulvarghese/aursearchbot'>Source Code</a> | \
                 <a href='https://t.me/bytesio'>Developer</a> | <a href='https://t.me/bytessupport'>Support Chat</a>",
                 )
-                .reply_markup(InlineKeyboardMarkup::new([[
-                    InlineKeyboardButton::switch_inline_query_current_chat("Search Packages", String::new()),
-                    InlineKeyboardButton::switch_inline_query_current_chat("Search Package by Maintainers", "!m "),
-                ]]))
+                .reply_markup(InlineKeyboardMarkup::new([
+                    [
+                        InlineKeyboardButton::switch_inline_query_current_chat("Search Packages", String::new()),
+                        InlineKeyboardButton::switch_inline_query_current_chat("Search by Maintainer", "!m "),
+                    ],
+                    [
+                        InlineKeyboardButton::switch_inline_query_current_chat("Search by Depends", "!d "),
+                        InlineKeyboardButton::switch_inline_query_current_chat("Search by Keywords", "!k "),
+                    ],
+                ]))
                 .parse_mode(ParseMode::Html)
                 .disable_link_preview(false)
                 .await?;
@@ -141,3 +175,126 @@ pub async fn message_handler(bot: Bot, message: Message) -> Result<(), RequestEr
     };
     respond(())
 }
+
+// escape characters that would otherwise break Telegram's HTML parse mode inside a <pre> block.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// handle the "Full Info" and "PKGBUILD" buttons attached to each inline result.
+pub async fn callback_queries_handler(bot: Bot, query: CallbackQuery, utils: Arc<Utils>) -> Result<(), RequestError> {
+    if let Some(package_name) = query.data.as_deref().and_then(|data| data.strip_prefix(INFO_CALLBACK_PREFIX)) {
+        let aur_response = cached_search(&utils, Search::Info(package_name.to_string(), Filter::None)).await;
+        let text = match &aur_response {
+            AurResponse::Result { results, .. } => results
+                .first()
+                .map(|package| package.pretty_full())
+                .unwrap_or_else(|| "Package not found".to_string()),
+            AurResponse::Error { error } => error.clone(),
+        };
+        if let Some(inline_message_id) = query.inline_message_id.clone() {
+            bot.edit_message_text_inline(inline_message_id, text)
+                .parse_mode(ParseMode::Html)
+                .await?;
+        }
+    } else if let Some(package_base) = query.data.as_deref().and_then(|data| data.strip_prefix(PKGBUILD_CALLBACK_PREFIX)) {
+        let mut toast = None;
+        match fetch_pkgbuild(&utils.client, package_base).await {
+            Ok(pkgbuild) => {
+                let (text, truncated) = render_pkgbuild(package_base, &pkgbuild);
+                // too large to show in full inline; mirror Command::Debug's document-sending path,
+                // falling back to the truncated+link text above if it fails — the bot is
+                // inline-only (see the /start text), so the presser may never have opened a
+                // private chat with us for bot.send_document to land in.
+                if truncated && send_pkgbuild_document(&bot, &query, package_base, &pkgbuild).await {
+                    toast = Some("PKGBUILD sent to your private chat".to_string());
+                } else if let Some(inline_message_id) = query.inline_message_id.clone() {
+                    bot.edit_message_text_inline(inline_message_id, text)
+                        .parse_mode(ParseMode::Html)
+                        .await?;
+                }
+            }
+            Err(error) => {
+                if let Some(inline_message_id) = query.inline_message_id.clone() {
+                    bot.edit_message_text_inline(inline_message_id, error).await?;
+                }
+            }
+        }
+        let mut answer = bot.answer_callback_query(query.id.clone());
+        if let Some(toast) = toast {
+            answer = answer.text(toast);
+        }
+        answer.await?;
+        return respond(());
+    }
+    bot.answer_callback_query(query.id).await?;
+    respond(())
+}
+
+// send the full PKGBUILD to the presser's private chat as a document, the same way
+// Command::Debug hands out debug.log. Returns false (instead of propagating the error) so the
+// caller can fall back to the truncated inline text when the presser has no private chat with us.
+async fn send_pkgbuild_document(bot: &Bot, query: &CallbackQuery, package_base: &str, pkgbuild: &str) -> bool {
+    let file_name = std::env::temp_dir().join(format!("{package_base}-PKGBUILD"));
+    if std::fs::write(&file_name, pkgbuild).is_err() {
+        return false;
+    }
+    let sent = bot
+        .send_document(ChatId(query.from.id.0 as i64), InputFile::file(&file_name))
+        .await;
+    let _ = std::fs::remove_file(&file_name);
+    sent.is_ok()
+}
+
+// render a PKGBUILD into an inline message, truncating with a link to the full file when it
+// wouldn't otherwise fit in a single Telegram message. Returns whether it had to truncate.
+fn render_pkgbuild(package_base: &str, pkgbuild: &str) -> (String, bool) {
+    let link = format!("<a href='{}'>View full PKGBUILD</a>", pkgbuild_url(package_base));
+    // escape before measuring/truncating: escaping expands `&`/`<`/`>`, so budgeting against the
+    // raw PKGBUILD length (as this used to) could leave the final message over the Telegram limit.
+    let escaped = escape_html(pkgbuild);
+    let wrapped = format!("<pre>{escaped}</pre>\n{link}");
+    if wrapped.len() <= TELEGRAM_MESSAGE_LIMIT {
+        return (wrapped, false);
+    }
+    let overhead = "<pre>\n…</pre>\n".len() + link.len();
+    let budget = TELEGRAM_MESSAGE_LIMIT.saturating_sub(overhead);
+    let mut truncated: String = escaped.chars().take(budget).collect();
+    // avoid cutting in the middle of an HTML entity (e.g. "&amp;" -> "&am"), which would leave a
+    // stray unescaped '&' and break Telegram's HTML parse mode.
+    if let Some(amp) = truncated.rfind('&') {
+        if !truncated[amp..].contains(';') {
+            truncated.truncate(amp);
+        }
+    }
+    (format!("<pre>{truncated}\n…</pre>\n{link}"), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_pkgbuild_fits_inline() {
+        let (text, truncated) = render_pkgbuild("foo", "pkgname=foo\npkgver=1.0\n");
+        assert!(!truncated);
+        assert!(text.len() <= TELEGRAM_MESSAGE_LIMIT);
+        assert!(text.starts_with("<pre>pkgname=foo"));
+    }
+
+    #[test]
+    fn test_render_pkgbuild_truncates_escaped_output_within_limit() {
+        // heavy on '&'/'<'/'>' so the escaped text is much larger than the raw text near the
+        // truncation boundary, which is exactly what broke the old raw-length budget.
+        let pkgbuild = "a && b ".repeat(2000);
+        let (text, truncated) = render_pkgbuild("foo", &pkgbuild);
+        assert!(truncated);
+        assert!(
+            text.len() <= TELEGRAM_MESSAGE_LIMIT,
+            "rendered message ({}) exceeds Telegram's limit ({})",
+            text.len(),
+            TELEGRAM_MESSAGE_LIMIT
+        );
+        assert!(!text.contains("&a\n"), "must not cut in the middle of an HTML entity");
+    }
+}
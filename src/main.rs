@@ -1,13 +1,14 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use retainer::Cache;
 use teloxide::dptree::endpoint;
 use teloxide::prelude::*;
 
-use handlers::{inline_queries_handler, message_handler};
-use request::{AurResponse, Search, Utils};
+use cache::PersistentCache;
+use handlers::{callback_queries_handler, inline_queries_handler, message_handler};
+use request::Utils;
 
+mod cache;
 mod handlers;
 mod request;
 
@@ -20,17 +21,28 @@ async fn main() {
 async fn run() {
     log::info!("Starting bot...");
     let bot = Bot::from_env().auto_send();
-    let cache: Arc<Cache<Search, AurResponse>> = Arc::new(Cache::new());
+    // CACHE_TTL_SECS lets a deployer tune how long a search result stays fresh before it's
+    // re-fetched from AUR; falls back to the previous retainer cache's 60s default.
+    let cache_ttl_secs = std::env::var("CACHE_TTL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(cache::DEFAULT_TTL_SECS);
+    let cache = Arc::new(PersistentCache::open_with_ttl("cache.sqlite3", cache_ttl_secs).unwrap());
     let utils = Arc::new(Utils::new(&cache));
 
-    tokio::spawn(async move { cache.monitor(4, 0.25, Duration::from_secs(15)).await });
+    tokio::spawn({
+        let cache = Arc::clone(&cache);
+        async move { cache.monitor(Duration::from_secs(15)).await }
+    });
 
     let inline_handler = Update::filter_inline_query().branch(endpoint(inline_queries_handler));
     let message_handler = Update::filter_message().branch(endpoint(message_handler));
+    let callback_handler = Update::filter_callback_query().branch(endpoint(callback_queries_handler));
 
     let handler = dptree::entry()
         .branch(message_handler)
-        .branch(inline_handler);
+        .branch(inline_handler)
+        .branch(callback_handler);
     Dispatcher::builder(bot, handler)
         .dependencies(dptree::deps![utils])
         .build()
@@ -3,28 +3,38 @@ use std::time::Duration;
 
 use chrono::NaiveDateTime;
 use lazy_static::lazy_static;
+use log::warn;
 use regex::Regex;
 use reqwest::Client;
-use retainer::{entry::CacheReadGuard, Cache};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
+use tokio::time::sleep;
+
+use crate::cache::PersistentCache;
 
 const AUR_RPC_URL: &str = "https://aur.archlinux.org/rpc/";
+// AUR can be slow during load spikes; give up rather than let an inline query hang forever.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// delays between retries of a transient AUR failure, tried before giving up.
+const RETRY_DELAYS: [Duration; 2] = [Duration::from_millis(200), Duration::from_millis(400)];
 
 pub struct Utils {
-    pub cache: Arc<Cache<Search, AurResponse>>,
+    pub cache: Arc<PersistentCache>,
     pub client: Client,
 }
 
 impl Utils {
-    pub fn new(cache: &Arc<Cache<Search, AurResponse>>) -> Self {
+    pub fn new(cache: &Arc<PersistentCache>) -> Self {
         Utils {
             cache: Arc::clone(cache),
-            client: Client::new(),
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("failed to build HTTP client"),
         }
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(tag = "type")]
 pub enum AurResponse {
     #[serde(rename = "error")]
@@ -37,18 +47,98 @@ pub enum AurResponse {
     },
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+// a filter token a user can append to a query, applied to results after the popularity sort.
+#[derive(Clone, Copy, Debug)]
+pub enum Filter {
+    None,
+    // maintainer == "None", i.e. nobody owns the package.
+    Orphan,
+    // out_of_date is set, i.e. the package was flagged stale.
+    Flagged,
+}
+
+#[derive(Clone)]
 pub enum Search {
-    Package(String),
-    Maintainer(String),
+    // the default for an unprefixed query, since most users mean "name or description" when they search.
+    NameDesc(String, Filter),
+    Maintainer(String, Filter),
+    Info(String, Filter),
+    Depends(String, Filter),
+    MakeDepends(String, Filter),
+    OptDepends(String, Filter),
+    CheckDepends(String, Filter),
+    Provides(String, Filter),
+    Conflicts(String, Filter),
+    Replaces(String, Filter),
+    Keywords(String, Filter),
 }
 
 impl Search {
     pub fn from(query: &str) -> Self {
-        if query.starts_with("!m ") {
-            Search::Maintainer(query.replace("!m ", ""))
+        let (query, filter) = if let Some(rest) = query.strip_suffix(" !orphan") {
+            (rest, Filter::Orphan)
+        } else if let Some(rest) = query.strip_suffix(" !flagged") {
+            (rest, Filter::Flagged)
         } else {
-            Search::Package(query.to_string())
+            (query, Filter::None)
+        };
+        if let Some(rest) = query.strip_prefix("!m ") {
+            Search::Maintainer(rest.to_string(), filter)
+        } else if let Some(rest) = query.strip_prefix("!i ") {
+            Search::Info(rest.to_string(), filter)
+        } else if let Some(rest) = query.strip_prefix("!nd ") {
+            Search::NameDesc(rest.to_string(), filter)
+        } else if let Some(rest) = query.strip_prefix("!md ") {
+            Search::MakeDepends(rest.to_string(), filter)
+        } else if let Some(rest) = query.strip_prefix("!od ") {
+            Search::OptDepends(rest.to_string(), filter)
+        } else if let Some(rest) = query.strip_prefix("!cd ") {
+            Search::CheckDepends(rest.to_string(), filter)
+        } else if let Some(rest) = query.strip_prefix("!d ") {
+            Search::Depends(rest.to_string(), filter)
+        } else if let Some(rest) = query.strip_prefix("!p ") {
+            Search::Provides(rest.to_string(), filter)
+        } else if let Some(rest) = query.strip_prefix("!c ") {
+            Search::Conflicts(rest.to_string(), filter)
+        } else if let Some(rest) = query.strip_prefix("!r ") {
+            Search::Replaces(rest.to_string(), filter)
+        } else if let Some(rest) = query.strip_prefix("!k ") {
+            Search::Keywords(rest.to_string(), filter)
+        } else {
+            Search::NameDesc(query.to_string(), filter)
+        }
+    }
+
+    pub fn filter(&self) -> Filter {
+        match self {
+            Search::NameDesc(_, filter) => *filter,
+            Search::Maintainer(_, filter) => *filter,
+            Search::Info(_, filter) => *filter,
+            Search::Depends(_, filter) => *filter,
+            Search::MakeDepends(_, filter) => *filter,
+            Search::OptDepends(_, filter) => *filter,
+            Search::CheckDepends(_, filter) => *filter,
+            Search::Provides(_, filter) => *filter,
+            Search::Conflicts(_, filter) => *filter,
+            Search::Replaces(_, filter) => *filter,
+            Search::Keywords(_, filter) => *filter,
+        }
+    }
+
+    // stable tag identifying the variant, used as part of the persistent cache key.
+    pub fn discriminant(&self) -> &'static str {
+        match self {
+            Search::NameDesc(..) => "name-desc",
+            Search::Maintainer(..) => "maintainer",
+            Search::Info(..) => "info",
+            Search::Depends(..) => "depends",
+            Search::MakeDepends(..) => "makedepends",
+            Search::OptDepends(..) => "optdepends",
+            Search::CheckDepends(..) => "checkdepends",
+            Search::Provides(..) => "provides",
+            Search::Conflicts(..) => "conflicts",
+            Search::Replaces(..) => "replaces",
+            Search::Keywords(..) => "keywords",
         }
     }
 }
@@ -57,13 +147,22 @@ impl std::ops::Deref for Search {
     type Target = String;
     fn deref(&self) -> &Self::Target {
         match &self {
-            Search::Package(x) => x,
-            Search::Maintainer(x) => x,
+            Search::NameDesc(x, _) => x,
+            Search::Maintainer(x, _) => x,
+            Search::Info(x, _) => x,
+            Search::Depends(x, _) => x,
+            Search::MakeDepends(x, _) => x,
+            Search::OptDepends(x, _) => x,
+            Search::CheckDepends(x, _) => x,
+            Search::Provides(x, _) => x,
+            Search::Conflicts(x, _) => x,
+            Search::Replaces(x, _) => x,
+            Search::Keywords(x, _) => x,
         }
     }
 }
 
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
 #[serde(rename_all = "PascalCase", default)]
 pub struct Packages {
     #[serde(rename = "ID")]
@@ -79,10 +178,19 @@ pub struct Packages {
     #[serde(rename = "URL", deserialize_with = "null_to_none")]
     pub package_url: String,
     pub package_base: String,
+    pub out_of_date: Option<i64>,
     #[serde(deserialize_with = "posix_to_datefmt")]
     pub first_submitted: String,
     #[serde(deserialize_with = "posix_to_datefmt")]
     pub last_modified: String,
+    pub depends: Vec<String>,
+    pub make_depends: Vec<String>,
+    pub opt_depends: Vec<String>,
+    pub check_depends: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub provides: Vec<String>,
+    pub license: Vec<String>,
+    pub keywords: Vec<String>,
 }
 
 // convert null type json objects to literal None and properly escape special characters.
@@ -121,8 +229,11 @@ where
     D: Deserializer<'de>,
 {
     let timestamp: i64 = Deserialize::deserialize(de)?;
-    let naive = NaiveDateTime::from_timestamp(timestamp, 0);
-    Ok(naive.format("%Y-%m-%d %H:%M").to_string())
+    Ok(format_epoch(timestamp))
+}
+
+fn format_epoch(timestamp: i64) -> String {
+    NaiveDateTime::from_timestamp(timestamp, 0).format("%Y-%m-%d %H:%M").to_string()
 }
 
 impl Packages {
@@ -130,13 +241,31 @@ impl Packages {
         format!("https://aur.archlinux.org/{}.git", self.package_base)
     }
 
+    pub fn pkgbuild_url(&self) -> String {
+        pkgbuild_url(&self.package_base)
+    }
+
+    // true for orphaned packages, i.e. no one has adopted them as maintainer.
+    pub fn is_orphan(&self) -> bool {
+        self.maintainer == "None"
+    }
+
     pub fn pretty(&self) -> String {
+        let maintainer = if self.is_orphan() {
+            "<i>None (orphaned)</i>".to_string()
+        } else {
+            format!("<code>{}</code>", &self.maintainer)
+        };
+        let out_of_date = match self.out_of_date {
+            Some(timestamp) => format!("\n⚠️ <b>Flagged out of date</b>: <code>{}</code>", format_epoch(timestamp)),
+            None => String::new(),
+        };
         format!(
             "📦 <b>{}</b>\n\n\
-            ℹ️{}\n\n\
+            ℹ️{}\n{}\n\
             🔗<a href='{}'>Git</a> | \
             <a href='{}'>Source</a>\n\
-            - Maintainer: <code>{}</code>\n\
+            - Maintainer: {}\n\
             - Votes: <code>{}</code>\n\
             - Version: <code>{}</code>\n\
             - Popularity: <code>{}</code>\n\
@@ -145,9 +274,10 @@ impl Packages {
             ",
             self.name,
             &self.description,
+            out_of_date,
             self.git(),
             &self.package_url,
-            &self.maintainer,
+            maintainer,
             self.num_votes,
             self.version,
             self.popularity,
@@ -155,36 +285,113 @@ impl Packages {
             &self.first_submitted,
         )
     }
+
+    // long-form view including the dependency groups, only populated by a `type=info` lookup.
+    pub fn pretty_full(&self) -> String {
+        format!(
+            "{}\n\
+            {}\n\
+            {}\n\
+            {}\n\
+            {}\n\
+            {}\n\
+            {}\n\
+            {}\n\
+            {}",
+            self.pretty(),
+            pretty_dep_group("Depends", &self.depends),
+            pretty_dep_group("Make Depends", &self.make_depends),
+            pretty_dep_group("Opt Depends", &self.opt_depends),
+            pretty_dep_group("Check Depends", &self.check_depends),
+            pretty_dep_group("Conflicts", &self.conflicts),
+            pretty_dep_group("Provides", &self.provides),
+            pretty_dep_group("License", &self.license),
+            pretty_dep_group("Keywords", &self.keywords),
+        )
+    }
+}
+
+// render a dependency-like group as a bullet list, or "None" when empty.
+fn pretty_dep_group(label: &str, items: &[String]) -> String {
+    if items.is_empty() {
+        format!("- {}: <code>None</code>", label)
+    } else {
+        format!("- {}:\n{}", label, items.iter().map(|item| format!("  • <code>{}</code>", item)).collect::<Vec<_>>().join("\n"))
+    }
 }
 
 pub async fn search(client: &Client, query: &Search) -> AurResponse {
-    let get_by = || match *query {
-        Search::Maintainer(_) => ("by", "maintainer"),
-        Search::Package(_) => ("by", "name"),
+    let request = match query {
+        Search::Info(name, _) => client
+            .get(AUR_RPC_URL)
+            .query(&[("v", "5"), ("type", "info"), ("arg[]", name.as_str())]),
+        _ => client.get(AUR_RPC_URL).query(&[
+            ("v", "5"),
+            ("type", "search"),
+            ("by", query.discriminant()),
+            ("arg", query.as_str()),
+        ]),
     };
-    let params = [("v", "5"), ("type", "search"), get_by(), ("arg", query)];
-    let res = client.get(AUR_RPC_URL).query(&params).send().await.unwrap();
-    res.json::<AurResponse>().await.unwrap()
+
+    let mut last_error = String::new();
+    for (attempt, delay) in std::iter::once(None).chain(RETRY_DELAYS.map(Some)).enumerate() {
+        if let Some(delay) = delay {
+            sleep(delay).await;
+        }
+        let Some(attempt_request) = request.try_clone() else {
+            break;
+        };
+        match attempt_request.send().await {
+            Ok(res) => match res.json::<AurResponse>().await {
+                Ok(response) => return response,
+                Err(err) => last_error = format!("decode error: {err}"),
+            },
+            Err(err) => last_error = format!("transport error: {err}"),
+        }
+        warn!("AUR request attempt {} failed: {}", attempt + 1, last_error);
+    }
+    AurResponse::Error {
+        error: "AUR is unreachable, try again".to_string(),
+    }
 }
 
-pub async fn cached_search(utils: &Utils, query: Search) -> CacheReadGuard<'_, AurResponse> {
+pub fn pkgbuild_url(package_base: &str) -> String {
+    format!("https://aur.archlinux.org/cgit/aur.git/plain/PKGBUILD?h={}", package_base)
+}
+
+// fetch the raw PKGBUILD for a package base so it can be audited before building.
+pub async fn fetch_pkgbuild(client: &Client, package_base: &str) -> Result<String, String> {
+    let res = client
+        .get(pkgbuild_url(package_base))
+        .send()
+        .await
+        .map_err(|_| "Failed to fetch PKGBUILD from AUR".to_string())?;
+    res.text().await.map_err(|_| "Failed to read PKGBUILD response".to_string())
+}
+
+pub async fn cached_search(utils: &Utils, query: Search) -> AurResponse {
     // check for cached entry
-    if let Some(cache) = utils.cache.get(&query).await {
-        cache
-    } else {
-        // if entry not found search the package in AUR
-        let mut response = search(&utils.client, &query).await;
-        if let AurResponse::Result { results, .. } = &mut response {
-            // sort result based on popularity
-            results.sort_by(|a, b| b.popularity.partial_cmp(&a.popularity).unwrap());
+    if let Some(response) = utils.cache.get(&query).await {
+        return response;
+    }
+    // if entry not found search the package in AUR
+    let mut response = search(&utils.client, &query).await;
+    if let AurResponse::Result { results, .. } = &mut response {
+        // sort result based on popularity
+        results.sort_by(|a, b| b.popularity.partial_cmp(&a.popularity).unwrap());
+        // apply any `!orphan` / `!flagged` filter the query carries
+        match query.filter() {
+            Filter::Orphan => results.retain(Packages::is_orphan),
+            Filter::Flagged => results.retain(|package| package.out_of_date.is_some()),
+            Filter::None => {}
         }
-        // add the result to cache
-        utils
-            .cache
-            .insert(query.clone(), response, Duration::from_secs(60))
-            .await;
-        utils.cache.get(&query).await.unwrap()
     }
+    // don't persist transient AUR failures — caching them would keep serving an error to every
+    // user making this query for the full TTL, long after AUR has recovered.
+    if !matches!(response, AurResponse::Error { .. }) {
+        utils.cache.insert(&query, &response).await;
+    }
+    response
 }
 
 #[cfg(test)]
@@ -192,19 +399,20 @@ mod tests {
 
     #[tokio::test]
     async fn test_request_functions() {
+        use crate::cache::PersistentCache;
         use crate::request::cached_search;
         use crate::request::{AurResponse, Search};
-        use crate::{Cache, Utils};
+        use crate::Utils;
         use std::sync::Arc;
 
-        let cache = Arc::new(Cache::new());
+        let cache = Arc::new(PersistentCache::open(":memory:").unwrap());
         let utils = Utils::new(&cache);
         let result = cached_search(&utils, Search::from("paru")).await;
         assert!(
-            matches!(*result, AurResponse::Result { .. },),
+            matches!(result, AurResponse::Result { .. },),
             "Search failed with a response of error variant"
         );
-        if let AurResponse::Result { results, total } = &*result {
+        if let AurResponse::Result { results, total } = &result {
             assert_ne!(*total, 0, "Number of packages returned from search is zero",);
 
             assert_eq!(results[0].name, "paru", "The packages sorting failed");
@@ -217,4 +425,34 @@ mod tests {
         let result = utils.cache.get(&Search::from("paru")).await;
         assert_ne!(matches!(result, None), true, "Couldn't find cache hit");
     }
+
+    #[test]
+    fn test_search_from_prefixes() {
+        use crate::request::{Filter, Search};
+
+        assert!(matches!(Search::from("firefox"), Search::NameDesc(q, Filter::None) if q == "firefox"));
+        assert!(matches!(Search::from("!m bob"), Search::Maintainer(q, Filter::None) if q == "bob"));
+        assert!(matches!(Search::from("!i firefox"), Search::Info(q, Filter::None) if q == "firefox"));
+        assert!(matches!(Search::from("!nd firefox"), Search::NameDesc(q, Filter::None) if q == "firefox"));
+        // `!cd` and `!c` share a leading character but must not be confused with each other.
+        assert!(matches!(Search::from("!cd pytest"), Search::CheckDepends(q, Filter::None) if q == "pytest"));
+        assert!(matches!(Search::from("!c bar"), Search::Conflicts(q, Filter::None) if q == "bar"));
+        // same for `!md`/`!m` and `!od`/`!d`.
+        assert!(matches!(Search::from("!md cmake"), Search::MakeDepends(q, Filter::None) if q == "cmake"));
+        assert!(matches!(Search::from("!od git"), Search::OptDepends(q, Filter::None) if q == "git"));
+        assert!(matches!(Search::from("!d qt5-base"), Search::Depends(q, Filter::None) if q == "qt5-base"));
+        assert!(matches!(Search::from("!p libfoo"), Search::Provides(q, Filter::None) if q == "libfoo"));
+        assert!(matches!(Search::from("!r baz"), Search::Replaces(q, Filter::None) if q == "baz"));
+        assert!(matches!(Search::from("!k rust"), Search::Keywords(q, Filter::None) if q == "rust"));
+    }
+
+    #[test]
+    fn test_search_from_filter_suffix() {
+        use crate::request::{Filter, Search};
+
+        assert!(matches!(Search::from("firefox !orphan"), Search::NameDesc(q, Filter::Orphan) if q == "firefox"));
+        assert!(matches!(Search::from("firefox !flagged"), Search::NameDesc(q, Filter::Flagged) if q == "firefox"));
+        assert!(matches!(Search::from("!m bob !orphan"), Search::Maintainer(q, Filter::Orphan) if q == "bob"));
+        assert!(matches!(Search::from("!d qt5-base !flagged"), Search::Depends(q, Filter::Flagged) if q == "qt5-base"));
+    }
 }